@@ -0,0 +1,145 @@
+use crate::{Error, FileFormat};
+use clap::Parser;
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+#[derive(Parser, Debug)]
+pub(crate) struct ConvertArgs {
+    /// Source file to convert (reads stdin if omitted)
+    #[clap(short)]
+    source: Option<PathBuf>,
+    /// Output file to write to (writes stdout if omitted)
+    #[clap(short)]
+    output: Option<PathBuf>,
+    /// Input format (inferred from the source extension if omitted)
+    #[clap(long, value_enum)]
+    from: Option<FileFormat>,
+    /// Output format
+    #[clap(long, value_enum)]
+    to: FileFormat,
+}
+
+pub(crate) fn run_convert(args: ConvertArgs) -> Result<(), Error> {
+    let from = match args.from {
+        Some(f) => f,
+        None => {
+            let source = args
+                .source
+                .as_ref()
+                .ok_or_else(|| Error::Msg("--from is required when reading from stdin!".into()))?;
+            FileFormat::from_ext(&source.extension().unwrap_or_default().to_string_lossy())?
+        }
+    };
+
+    let input = match &args.source {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| {
+            Error::IO(format!(
+                "Failed to read source file '{}': {}",
+                path.display(),
+                e
+            ))
+        })?,
+        None => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| Error::IO(format!("Failed to read stdin: {}", e)))?;
+            input
+        }
+    };
+
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path).map_err(|e| {
+            Error::IO(format!(
+                "Failed to create output file '{}': {}",
+                path.display(),
+                e
+            ))
+        })?),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    transcode(from, args.to, &input, &mut output)
+}
+
+/// Streams `input` from `from` straight into `to` via `serde_transcode`, without
+/// materializing a full intermediate value. The only exception is JSON5: JSON5
+/// input is read into a `serde_json::Value` before transcoding, and JSON5 output
+/// is built as a `serde_json::Value` and written with `json5::to_string`.
+fn transcode(from: FileFormat, to: FileFormat, input: &str, output: &mut dyn Write) -> Result<(), Error> {
+    match from {
+        FileFormat::Json => {
+            let de = &mut serde_json::Deserializer::from_str(input);
+            transcode_into(de, to, output)
+        }
+        FileFormat::Yaml => {
+            let de = serde_yaml::Deserializer::from_str(input);
+            transcode_into(de, to, output)
+        }
+        FileFormat::Toml => {
+            let de = toml::Deserializer::new(input);
+            transcode_into(de, to, output)
+        }
+        FileFormat::Ron => {
+            let mut de = ron::Deserializer::from_str(input).map_err(|e| {
+                Error::Deserialization(format!("Failed RON deserialization: {}", e))
+            })?;
+            transcode_into(&mut de, to, output)
+        }
+        FileFormat::Json5 => {
+            let mut de = json5::Deserializer::from_str(input).map_err(|e| {
+                Error::Deserialization(format!("Failed JSON5 deserialization: {}", e))
+            })?;
+            let value = serde_json::Value::deserialize(&mut de)
+                .map_err(|e| Error::Deserialization(format!("Failed JSON5 deserialization: {}", e)))?;
+            transcode_into(value, to, output)
+        }
+    }
+}
+
+fn transcode_into<'de, D>(deserializer: D, to: FileFormat, output: &mut dyn Write) -> Result<(), Error>
+where
+    D: serde::Deserializer<'de>,
+    D::Error: std::fmt::Display,
+{
+    match to {
+        FileFormat::Json => {
+            let mut serializer = serde_json::Serializer::pretty(output);
+            serde_transcode::transcode(deserializer, &mut serializer)
+                .map_err(|e| Error::Deserialization(format!("Failed JSON transcode: {}", e)))
+        }
+        FileFormat::Yaml => {
+            let mut serializer = serde_yaml::Serializer::new(output);
+            serde_transcode::transcode(deserializer, &mut serializer)
+                .map_err(|e| Error::Deserialization(format!("Failed YAML transcode: {}", e)))
+        }
+        FileFormat::Toml => {
+            let mut buf = String::new();
+            let serializer = toml::Serializer::new(&mut buf);
+            serde_transcode::transcode(deserializer, serializer)
+                .map_err(|e| Error::Deserialization(format!("Failed TOML transcode: {}", e)))?;
+            output
+                .write_all(buf.as_bytes())
+                .map_err(|e| Error::IO(format!("Failed to write output: {}", e)))
+        }
+        FileFormat::Ron => {
+            let mut serializer = ron::Serializer::new(output, None)
+                .map_err(|e| Error::Deserialization(format!("Failed RON transcode: {}", e)))?;
+            serde_transcode::transcode(deserializer, &mut serializer)
+                .map_err(|e| Error::Deserialization(format!("Failed RON transcode: {}", e)))
+        }
+        FileFormat::Json5 => {
+            let value = serde_json::Value::deserialize(deserializer)
+                .map_err(|e| Error::Deserialization(format!("Failed JSON5 transcode: {}", e)))?;
+            let s = json5::to_string(&value)
+                .map_err(|e| Error::Deserialization(format!("Failed JSON5 transcode: {}", e)))?;
+            output
+                .write_all(s.as_bytes())
+                .map_err(|e| Error::IO(format!("Failed to write output: {}", e)))
+        }
+    }
+}
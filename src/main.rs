@@ -1,35 +1,85 @@
-use clap::{Parser, ValueEnum};
+use base64::Engine;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::{
+    collections::HashMap,
     error::Error as StdError,
     fmt::Debug,
     fs::File,
     io::{self, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use tera::{Context, Tera};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 use thiserror::Error;
 
+mod convert;
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
-struct Args {
-    /// Source files
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Render templates from one or more data sources
+    Render(RenderArgs),
+    /// Convert a document from one format to another, without rendering
+    Convert(convert::ConvertArgs),
+}
+
+#[derive(Parser, Debug)]
+struct RenderArgs {
+    /// Source files, optionally as `name=path` to control the namespace key
     #[clap(short)]
-    sources: Option<Vec<PathBuf>>,
+    sources: Option<Vec<String>>,
     /// Output directory (required on template glob)
     #[clap(short)]
     out_dir: Option<PathBuf>,
     /// File format of the input (required on stdin source)
     #[clap(short, value_enum)]
     format: Option<FileFormat>,
+    /// How to combine arrays when the same key appears in multiple sources
+    #[clap(long, value_enum, default_value = "replace")]
+    array_merge: ArrayMerge,
+    /// Merge environment variables starting with this prefix into the context,
+    /// overriding file sources (e.g. APP__DB__PORT=5432 -> { db: { port: 5432 } })
+    #[clap(long)]
+    env_prefix: Option<String>,
+    /// Separator used to split environment variable names into nested keys
+    #[clap(long, default_value = "__")]
+    env_separator: String,
+    /// Nest each source under its own context key (derived from its file stem,
+    /// or the `name` in a `name=path` source) instead of merging them together
+    #[clap(long)]
+    namespace: bool,
+    /// Root directory that `read_file` and `--assets` paths are resolved against
+    #[clap(long, default_value = ".")]
+    asset_root: PathBuf,
+    /// Glob of asset files to expose as a `files` array in the context
+    #[clap(long)]
+    assets: Option<String>,
     /// Template file or glob
     templates: String,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum ArrayMerge {
+    /// The later source's array replaces the earlier one entirely
+    Replace,
+    /// The later source's array is appended to the earlier one
+    Concat,
+}
+
 fn main() {
-    let args = Args::parse();
+    let cli = Cli::parse();
     let mut stderr = StandardStream::stderr(ColorChoice::Auto);
-    if let Err(e) = run(args, &mut stderr) {
+    let result = match cli.command {
+        Command::Render(args) => run_render(args, &mut stderr),
+        Command::Convert(args) => convert::run_convert(args),
+    };
+    if let Err(e) = result {
         stderr
             .set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true))
             .unwrap();
@@ -40,7 +90,7 @@ fn main() {
 }
 
 #[derive(Error, Debug)]
-enum Error {
+pub(crate) enum Error {
     #[error("IO: {0}")]
     IO(String),
     #[error("{0}")]
@@ -63,11 +113,23 @@ impl std::convert::From<tera::Error> for Error {
     }
 }
 
-fn run(args: Args, stderr: &mut StandardStream) -> Result<(), Error> {
-    let sources: Vec<(FileFormat, String)> = {
+fn run_render(args: RenderArgs, stderr: &mut StandardStream) -> Result<(), Error> {
+    let sources: Vec<(FileFormat, String, String, String)> = {
         if let Some(sources) = args.sources {
             let mut inputs = Vec::new();
-            for source_path in &sources {
+            for source in &sources {
+                let (namespace_key, path_str) = match source.split_once('=') {
+                    Some((name, path)) => (name.to_string(), path),
+                    None => (
+                        PathBuf::from(source)
+                            .file_stem()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string(),
+                        source.as_str(),
+                    ),
+                };
+                let source_path = PathBuf::from(path_str);
                 let format = if let Some(f) = args.format {
                     f
                 } else {
@@ -78,14 +140,19 @@ fn run(args: Args, stderr: &mut StandardStream) -> Result<(), Error> {
                             .to_string_lossy(),
                     )?
                 };
-                let source_str = std::fs::read_to_string(source_path).map_err(|e| {
+                let source_str = std::fs::read_to_string(&source_path).map_err(|e| {
                     Error::IO(format!(
                         "Failed to read source file '{}': {}",
                         source_path.display(),
                         e
                     ))
                 })?;
-                inputs.push((format, source_str));
+                inputs.push((
+                    format,
+                    source_str,
+                    source_path.display().to_string(),
+                    namespace_key,
+                ));
             }
             inputs
         } else {
@@ -97,14 +164,28 @@ fn run(args: Args, stderr: &mut StandardStream) -> Result<(), Error> {
             stdin
                 .read_to_string(&mut input_str)
                 .map_err(|e| Error::IO(format!("Failed to read stdin: {}", e)))?;
-            vec![(format, input_str)]
+            vec![(
+                format,
+                input_str,
+                "<stdin>".to_string(),
+                "stdin".to_string(),
+            )]
         }
     };
-    let context = deserialize(&sources)?;
+    let mut root = deserialize(&sources, args.array_merge, args.namespace)?;
+    if let Some(prefix) = &args.env_prefix {
+        let env_value = env_overlay(prefix, &args.env_separator)?;
+        merge_values(&mut root, env_value, args.array_merge);
+    }
+    let mut context = Context::from_value(root)?;
+    if let Some(assets) = &args.assets {
+        context.insert("files", &collect_assets(assets, &args.asset_root)?);
+    }
 
     if let Some(out_dir) = args.out_dir {
         let mut tera = Tera::new(&args.templates)?;
         tera.autoescape_on(vec![]);
+        register_read_file(&mut tera, args.asset_root.clone());
         std::fs::create_dir_all(&out_dir).map_err(|e| {
             Error::IO(format!(
                 "Failed to create directories '{}': {}",
@@ -143,6 +224,7 @@ fn run(args: Args, stderr: &mut StandardStream) -> Result<(), Error> {
         })?;
         let mut tera = Tera::default();
         tera.autoescape_on(vec![]);
+        register_read_file(&mut tera, args.asset_root.clone());
         tera.add_raw_template(&args.templates, &template_input)?;
         let mut stdout = io::stdout().lock();
         tera.render_to(&args.templates, &context, &mut stdout)?;
@@ -151,38 +233,324 @@ fn run(args: Args, stderr: &mut StandardStream) -> Result<(), Error> {
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd, ValueEnum)]
-enum FileFormat {
+pub(crate) enum FileFormat {
     Json,
     Yaml,
     Toml,
+    Ron,
+    Json5,
 }
 
 impl FileFormat {
-    fn from_ext(s: &str) -> Result<Self, Error> {
-        return match s.trim().to_lowercase().as_str() {
+    pub(crate) fn from_ext(s: &str) -> Result<Self, Error> {
+        match s.trim().to_lowercase().as_str() {
             "json" => Ok(Self::Json),
             "yaml" | "yml" => Ok(Self::Yaml),
             "toml" => Ok(Self::Toml),
+            "ron" => Ok(Self::Ron),
+            "json5" => Ok(Self::Json5),
             _ => Err(Error::UnsupportedExt(s.to_string())),
-        };
+        }
     }
 }
 
-fn deserialize(input: &Vec<(FileFormat, String)>) -> Result<Context, Error> {
-    let mut context = Context::new();
-    for (format, str) in input {
+fn deserialize(
+    input: &Vec<(FileFormat, String, String, String)>,
+    array_merge: ArrayMerge,
+    namespace: bool,
+) -> Result<serde_json::Value, Error> {
+    let mut root = serde_json::Value::Object(serde_json::Map::new());
+    for (format, str, name, namespace_key) in input {
         let value: serde_json::Value = match format {
-            FileFormat::Json => serde_json::from_str::<serde_json::Value>(str).map_err(|e| {
-                Error::Deserialization(format!("Failed JSON deserialization: {}", e))
-            })?,
-            FileFormat::Yaml => serde_yaml::from_str::<serde_json::Value>(str).map_err(|e| {
-                Error::Deserialization(format!("Failed YAML deserialization: {}", e))
-            })?,
-            FileFormat::Toml => toml::from_str::<serde_json::Value>(str).map_err(|e| {
-                Error::Deserialization(format!("Failed TOML deserialization: {}", e))
-            })?,
+            FileFormat::Json => {
+                let de = &mut serde_json::Deserializer::from_str(str);
+                serde_path_to_error::deserialize(de).map_err(|e| {
+                    Error::Deserialization(format!(
+                        "Failed JSON deserialization of '{}' at '{}': {}",
+                        name,
+                        e.path(),
+                        e
+                    ))
+                })?
+            }
+            FileFormat::Yaml => {
+                let de = serde_yaml::Deserializer::from_str(str);
+                serde_path_to_error::deserialize(de).map_err(|e| {
+                    Error::Deserialization(format!(
+                        "Failed YAML deserialization of '{}' at '{}': {}",
+                        name,
+                        e.path(),
+                        e
+                    ))
+                })?
+            }
+            FileFormat::Toml => {
+                let de = toml::Deserializer::new(str);
+                serde_path_to_error::deserialize(de).map_err(|e| {
+                    Error::Deserialization(format!(
+                        "Failed TOML deserialization of '{}' at '{}': {}",
+                        name,
+                        e.path(),
+                        e
+                    ))
+                })?
+            }
+            FileFormat::Ron => {
+                let mut de = ron::Deserializer::from_str(str).map_err(|e| {
+                    Error::Deserialization(format!(
+                        "Failed RON deserialization of '{}': {}",
+                        name, e
+                    ))
+                })?;
+                serde_path_to_error::deserialize(&mut de).map_err(|e| {
+                    Error::Deserialization(format!(
+                        "Failed RON deserialization of '{}' at '{}': {}",
+                        name,
+                        e.path(),
+                        e
+                    ))
+                })?
+            }
+            FileFormat::Json5 => {
+                let mut de = json5::Deserializer::from_str(str).map_err(|e| {
+                    Error::Deserialization(format!(
+                        "Failed JSON5 deserialization of '{}': {}",
+                        name, e
+                    ))
+                })?;
+                serde_path_to_error::deserialize(&mut de).map_err(|e| {
+                    Error::Deserialization(format!(
+                        "Failed JSON5 deserialization of '{}' at '{}': {}",
+                        name,
+                        e.path(),
+                        e
+                    ))
+                })?
+            }
         };
-        context.extend(Context::from_value(value)?);
+        if namespace {
+            let serde_json::Value::Object(map) = &mut root else {
+                unreachable!("root is always built as Value::Object above")
+            };
+            match map.get_mut(namespace_key) {
+                Some(existing) => merge_values(existing, value, array_merge),
+                None => {
+                    map.insert(namespace_key.clone(), value);
+                }
+            }
+        } else {
+            merge_values(&mut root, value, array_merge);
+        }
+    }
+    Ok(root)
+}
+
+/// Resolves `path` against `asset_root` and verifies the result is actually
+/// contained in it, rejecting absolute paths and `..` escapes (whether typed
+/// directly or reached through a symlink) before any file is touched.
+fn resolve_under_root(asset_root: &Path, path: &str) -> Result<PathBuf, String> {
+    if Path::new(path).is_absolute() {
+        return Err(format!(
+            "'{}' must be relative to the asset root, not absolute",
+            path
+        ));
+    }
+    let full_path = asset_root.join(path);
+    let canonical_root = asset_root
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve asset root '{}': {}", asset_root.display(), e))?;
+    let canonical_path = full_path
+        .canonicalize()
+        .map_err(|e| format!("failed to read '{}': {}", full_path.display(), e))?;
+    if !canonical_path.starts_with(&canonical_root) {
+        return Err(format!(
+            "'{}' escapes the asset root '{}'",
+            path,
+            asset_root.display()
+        ));
+    }
+    Ok(canonical_path)
+}
+
+/// Registers a `read_file(path, base64=false)` Tera function that reads a file
+/// relative to `asset_root`, so templates can inline snippets, licenses or
+/// partial data without a shell preprocessing step. `path` is confined to
+/// `asset_root`: absolute paths and `..` escapes are rejected.
+fn register_read_file(tera: &mut Tera, asset_root: PathBuf) {
+    tera.register_function(
+        "read_file",
+        move |args: &HashMap<String, tera::Value>| -> tera::Result<tera::Value> {
+            let path = args
+                .get("path")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| tera::Error::msg("read_file: missing 'path' argument"))?;
+            let base64 = args
+                .get("base64")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let full_path = resolve_under_root(&asset_root, path)
+                .map_err(|e| tera::Error::msg(format!("read_file: {}", e)))?;
+            let bytes = std::fs::read(&full_path).map_err(|e| {
+                tera::Error::msg(format!(
+                    "read_file: failed to read '{}': {}",
+                    full_path.display(),
+                    e
+                ))
+            })?;
+            if base64 {
+                Ok(tera::Value::String(
+                    base64::engine::general_purpose::STANDARD.encode(bytes),
+                ))
+            } else {
+                let text = String::from_utf8(bytes).map_err(|e| {
+                    tera::Error::msg(format!(
+                        "read_file: '{}' is not valid UTF-8, pass base64=true: {}",
+                        full_path.display(),
+                        e
+                    ))
+                })?;
+                Ok(tera::Value::String(text))
+            }
+        },
+    );
+}
+
+/// Walks `assets_glob` and returns the matched file paths relative to
+/// `asset_root`, for injection into the context as `files`. `assets_glob` is
+/// resolved against `asset_root` (it must be relative), and every match is
+/// confined to `asset_root` the same way `resolve_under_root` confines
+/// `read_file`, so `files` entries are always valid `read_file` arguments.
+fn collect_assets(assets_glob: &str, asset_root: &Path) -> Result<Vec<String>, Error> {
+    if Path::new(assets_glob).is_absolute() {
+        return Err(Error::Msg(format!(
+            "Assets glob '{}' must be relative to the asset root, not absolute",
+            assets_glob
+        )));
+    }
+    let canonical_root = asset_root.canonicalize().map_err(|e| {
+        Error::IO(format!(
+            "Failed to resolve asset root '{}': {}",
+            asset_root.display(),
+            e
+        ))
+    })?;
+    let rooted_glob = asset_root.join(assets_glob);
+    let mut files = Vec::new();
+    for entry in glob::glob(&rooted_glob.to_string_lossy())
+        .map_err(|e| Error::Msg(format!("Invalid assets glob '{}': {}", assets_glob, e)))?
+    {
+        let path = entry.map_err(|e| Error::IO(format!("Failed to read assets entry: {}", e)))?;
+        if path.is_file() {
+            let canonical_path = path.canonicalize().map_err(|e| {
+                Error::IO(format!(
+                    "Failed to resolve assets entry '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            if !canonical_path.starts_with(&canonical_root) {
+                return Err(Error::Msg(format!(
+                    "Assets entry '{}' escapes the asset root '{}'",
+                    canonical_path.display(),
+                    asset_root.display()
+                )));
+            }
+            let relative = canonical_path
+                .strip_prefix(&canonical_root)
+                .unwrap_or(&canonical_path);
+            files.push(relative.display().to_string());
+        }
+    }
+    Ok(files)
+}
+
+/// Builds a nested JSON object from the process environment, collecting every
+/// variable starting with `prefix`, stripping it, lowercasing the remainder and
+/// splitting on `separator` into nested keys. Values are parsed as JSON scalars
+/// (numbers/bools) where possible, falling back to plain strings.
+///
+/// `std::env::vars()` iterates in an unspecified order, so two variables whose
+/// paths collide (one treating a segment as a scalar, the other descending
+/// into it as an object) are a real, order-dependent conflict rather than an
+/// invariant violation: that case is reported as an `Error` instead of panicking.
+fn env_overlay(prefix: &str, separator: &str) -> Result<serde_json::Value, Error> {
+    let mut root = serde_json::Value::Object(serde_json::Map::new());
+    for (key, value) in std::env::vars() {
+        let Some(rest) = key.strip_prefix(prefix) else {
+            continue;
+        };
+        let path: Vec<String> = rest
+            .to_lowercase()
+            .split(separator)
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        if path.is_empty() {
+            continue;
+        }
+        let value = match serde_json::from_str::<serde_json::Value>(&value) {
+            Ok(scalar @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_))) => scalar,
+            _ => serde_json::Value::String(value),
+        };
+
+        let mut cursor = &mut root;
+        for (depth, segment) in path[..path.len() - 1].iter().enumerate() {
+            let serde_json::Value::Object(map) = cursor else {
+                return Err(Error::Msg(format!(
+                    "Environment variable '{}' conflicts with another variable that treats '{}' as a plain value",
+                    key,
+                    path[..depth].join(".")
+                )));
+            };
+            cursor = map
+                .entry(segment.clone())
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        }
+        let serde_json::Value::Object(map) = cursor else {
+            return Err(Error::Msg(format!(
+                "Environment variable '{}' conflicts with another variable that treats its parent as a plain value",
+                key
+            )));
+        };
+        let leaf = &path[path.len() - 1];
+        if let Some(serde_json::Value::Object(_)) = map.get(leaf) {
+            return Err(Error::Msg(format!(
+                "Environment variable '{}' conflicts with another variable that treats '{}' as an object",
+                key,
+                path.join(".")
+            )));
+        }
+        map.insert(leaf.clone(), value);
+    }
+    Ok(root)
+}
+
+/// Recursively merges `incoming` into `base`. Objects are merged key-by-key;
+/// anything else (scalars, type mismatches, and arrays under `ArrayMerge::Replace`)
+/// is overwritten by the later source, matching layered config providers like
+/// the `config` crate.
+fn merge_values(
+    base: &mut serde_json::Value,
+    incoming: serde_json::Value,
+    array_merge: ArrayMerge,
+) {
+    use serde_json::Value;
+    match (base, incoming) {
+        (Value::Object(base_map), Value::Object(incoming_map)) => {
+            for (key, incoming_value) in incoming_map {
+                match base_map.get_mut(&key) {
+                    Some(base_value) => merge_values(base_value, incoming_value, array_merge),
+                    None => {
+                        base_map.insert(key, incoming_value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base_arr), Value::Array(incoming_arr))
+            if array_merge == ArrayMerge::Concat =>
+        {
+            base_arr.extend(incoming_arr);
+        }
+        (base, incoming) => *base = incoming,
     }
-    Ok(context)
 }